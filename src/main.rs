@@ -1,16 +1,21 @@
-use reqwest;
 use std::collections::HashMap;
 use hmac::{Hmac, Mac, NewMac};
 use sha2::{Sha256, Sha512};
 use base64::{decode, encode};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use chrono::Utc;
-use tokio;
 use std::sync::Arc;
+use std::path::PathBuf;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use fs2::FileExt;
 use sha2::Digest;
 
 type HmacSha512 = Hmac<Sha512>;
 
+const DEFAULT_BASE_URL: &str = "https://api.kraken.com";
+
 // Data structure for API response for balance
 #[derive(Deserialize, Debug)]
 struct BalanceResponse {
@@ -25,174 +30,603 @@ struct OrderResponse {
     result: Option<HashMap<String, String>>,
 }
 
+/// A single pair's entry from Kraken's public `Ticker` endpoint. Prices and
+/// volumes are kept as the strings Kraken returns them as, to avoid losing
+/// precision to floating point.
+#[derive(Deserialize, Debug)]
+struct TickerInfo {
+    /// `[price, whole lot volume, lot volume]`
+    #[serde(rename = "a")]
+    ask: Vec<String>,
+    /// `[price, whole lot volume, lot volume]`
+    #[serde(rename = "b")]
+    bid: Vec<String>,
+    /// `[price, lot volume]` of the last trade.
+    #[serde(rename = "c")]
+    last_trade: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TickerResponse {
+    error: Vec<String>,
+    result: Option<HashMap<String, TickerInfo>>,
+}
+
+/// One level of an order book: `(price, volume, timestamp)`.
+#[derive(Deserialize, Debug)]
+struct OrderBookLevel(String, String, i64);
+
+/// A single pair's entry from Kraken's public `Depth` endpoint.
+#[derive(Deserialize, Debug)]
+struct OrderBookInfo {
+    asks: Vec<OrderBookLevel>,
+    bids: Vec<OrderBookLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct OrderBookResponse {
+    error: Vec<String>,
+    result: Option<HashMap<String, OrderBookInfo>>,
+}
+
+/// Which side of the book an order is placed on.
+#[derive(Debug, Clone, Copy)]
+enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    fn as_kraken_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "buy",
+            OrderSide::Sell => "sell",
+        }
+    }
+}
+
+/// The Kraken `ordertype` values `add_order` knows how to fill in.
+#[derive(Debug, Clone, Copy)]
+enum OrderType {
+    Market,
+    Limit,
+    StopLoss,
+    TakeProfit,
+}
+
+impl OrderType {
+    fn as_kraken_str(&self) -> &'static str {
+        match self {
+            OrderType::Market => "market",
+            OrderType::Limit => "limit",
+            OrderType::StopLoss => "stop-loss",
+            OrderType::TakeProfit => "take-profit",
+        }
+    }
+}
+
+/// Mirrors Kraken's `timeinforce` order flag.
+#[derive(Debug, Clone, Copy)]
+enum TimeInForce {
+    GoodTillCancel,
+    ImmediateOrCancel,
+    GoodTillDate,
+}
+
+impl TimeInForce {
+    fn as_kraken_str(&self) -> &'static str {
+        match self {
+            TimeInForce::GoodTillCancel => "GTC",
+            TimeInForce::ImmediateOrCancel => "IOC",
+            TimeInForce::GoodTillDate => "GTD",
+        }
+    }
+}
+
+/// Describes an order to submit via `KrakenClient::add_order`. Construct with
+/// `OrderRequest::new` for the required fields, then chain setters for the
+/// optional ones (limit/stop price, time in force, user reference).
+#[derive(Debug, Clone)]
+struct OrderRequest {
+    pair: String,
+    side: OrderSide,
+    order_type: OrderType,
+    volume: String,
+    price: Option<String>,
+    stop_price: Option<String>,
+    time_in_force: Option<TimeInForce>,
+    user_ref: Option<u32>,
+    validate: bool,
+}
+
+impl OrderRequest {
+    fn new(pair: impl Into<String>, side: OrderSide, order_type: OrderType, volume: impl Into<String>) -> Self {
+        Self {
+            pair: pair.into(),
+            side,
+            order_type,
+            volume: volume.into(),
+            price: None,
+            stop_price: None,
+            time_in_force: None,
+            user_ref: None,
+            validate: false,
+        }
+    }
+
+    /// Sets the limit price for a `Limit` order.
+    fn price(mut self, price: impl Into<String>) -> Self {
+        self.price = Some(price.into());
+        self
+    }
+
+    /// Sets the trigger price for `StopLoss`/`TakeProfit` orders.
+    fn stop_price(mut self, stop_price: impl Into<String>) -> Self {
+        self.stop_price = Some(stop_price.into());
+        self
+    }
+
+    fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    /// Sets a caller-chosen reference id, sent to Kraken as `userref` and
+    /// echoed back in order info/cancellations.
+    fn user_ref(mut self, user_ref: u32) -> Self {
+        self.user_ref = Some(user_ref);
+        self
+    }
+
+    /// Marks this as a validate-only (dry-run) order: Kraken checks the
+    /// parameters without routing it to the matching engine.
+    fn validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+}
+
+/// Errors that can arise while talking to the Kraken API. Library consumers
+/// can match on the variant instead of scraping stdout: a malformed secret or
+/// HMAC key surfaces as `Signature`, a transport failure as `Http`, a body
+/// that isn't valid JSON as `Decode`, and a non-empty Kraken `error` array as
+/// `Api`.
+#[derive(Debug)]
+enum KrakenError {
+    Http(reqwest::Error),
+    Decode(serde_json::Error),
+    Api(Vec<String>),
+    Signature(String),
+}
+
+impl std::fmt::Display for KrakenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KrakenError::Http(e) => write!(f, "HTTP error: {}", e),
+            KrakenError::Decode(e) => write!(f, "failed to decode response: {}", e),
+            KrakenError::Api(errors) => write!(f, "Kraken API error: {}", errors.join(", ")),
+            KrakenError::Signature(message) => write!(f, "failed to sign request: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for KrakenError {}
+
+impl From<reqwest::Error> for KrakenError {
+    fn from(error: reqwest::Error) -> Self {
+        KrakenError::Http(error)
+    }
+}
+
+impl From<serde_json::Error> for KrakenError {
+    fn from(error: serde_json::Error) -> Self {
+        KrakenError::Decode(error)
+    }
+}
+
 // Function to generate signature for API requests
-fn generate_signature(api_secret: &str, nonce: &str, endpoint: &str, post_data: &str) -> String {
+fn generate_signature(api_secret: &str, nonce: &str, endpoint: &str, post_data: &str) -> Result<String, KrakenError> {
     // Decode API secret from base64
-    let api_secret_decoded = decode(api_secret).expect("Invalid base64 API secret");
-    
+    let api_secret_decoded =
+        decode(api_secret).map_err(|e| KrakenError::Signature(format!("invalid base64 API secret: {}", e)))?;
+
     // Create SHA256 hash
     let mut sha256 = Sha256::new();
     sha256.update(format!("{}{}", nonce, post_data).as_bytes());
     let hash = sha256.finalize();
-    
+
     // Create HMAC-SHA512 signature
-    let mut mac = HmacSha512::new_from_slice(&api_secret_decoded).expect("HMAC can take key of any size");
+    let mut mac = HmacSha512::new_from_slice(&api_secret_decoded)
+        .map_err(|e| KrakenError::Signature(format!("invalid HMAC key: {}", e)))?;
     mac.update(endpoint.as_bytes());
     mac.update(&hash);
-    
+
     // Encode signature to base64
-    encode(mac.finalize().into_bytes())
-}
-
-// Function to fetch account balance
-async fn fetch_balance(api_key: &str, api_secret: &str) -> Result<Option<HashMap<String, String>>, Box<dyn std::error::Error + Send + Sync>> {
-    let url = "https://api.kraken.com/0/private/Balance";
-    let endpoint = "/0/private/Balance";
-    let client = reqwest::Client::new();
-    
-    // Generate nonce using current timestamp in milliseconds
-    let nonce = format!("{}", Utc::now().timestamp_millis());
-    let mut params = HashMap::new();
-    params.insert("nonce", nonce.clone());
-    
-    // Create post data string
-    let post_data = format!("nonce={}", nonce);
-    
-    // Generate API signature
-    let api_sign = generate_signature(api_secret, &nonce, endpoint, &post_data);
-    
-    // Send POST request to fetch balance
-    let response = client
-        .post(url)
-        .header("API-Key", api_key)
-        .header("API-Sign", api_sign)
-        .form(&params)
-        .send()
-        .await?;
-    
-    // Print response status and body for debugging
-    let status = response.status();
-    let body = response.text().await?;
-    println!("Response status: {}", status);
-    println!("Response body: {}", body);
-
-    // Process the response if successful
-    if status.is_success() {
-        let balance: BalanceResponse = serde_json::from_str(&body)?;
-        if balance.error.is_empty() {
-            if let Some(result) = balance.result {
-                println!("Balance fetched successfully");
-                for (currency, amount) in result.iter() {
-                    println!("{}: {}", currency, amount);
+    Ok(encode(mac.finalize().into_bytes()))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A strictly-increasing, crash-persistent nonce source for a single API key.
+///
+/// Kraken rejects any nonce that is not strictly greater than the last one it
+/// saw for that key, and two requests firing in the same millisecond (or a
+/// clock step backward) would otherwise produce a duplicate. `next()` always
+/// returns `max(now_ms, last + 1)` and persists the result to disk, under an
+/// exclusive file lock, before handing it out, so nonces never repeat or
+/// regress across process restarts or between two processes sharing a key —
+/// regardless of each process's current working directory.
+struct NonceManager {
+    store_path: PathBuf,
+    // Serializes `next()` calls within this process; the file lock below
+    // serializes them across processes.
+    lock: std::sync::Mutex<()>,
+}
+
+impl NonceManager {
+    /// Prepares the persisted nonce store for `api_key`. Each key gets its own
+    /// store file, anchored next to the running binary rather than the
+    /// process's current directory, so unrelated keys don't contend on one
+    /// counter and a restart from a different cwd can't lose the last value.
+    fn new(api_key: &str) -> Self {
+        Self {
+            store_path: Self::store_path_for(api_key),
+            lock: std::sync::Mutex::new(()),
+        }
+    }
+
+    fn state_dir() -> PathBuf {
+        std::env::current_exe()
+            .ok()
+            .and_then(|path| path.parent().map(PathBuf::from))
+            .unwrap_or_else(std::env::temp_dir)
+    }
+
+    fn store_path_for(api_key: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(api_key.as_bytes());
+        Self::state_dir().join(format!(".kraken_nonce_{}", hex_encode(&hasher.finalize())))
+    }
+
+    /// Returns the next nonce to use, guaranteed strictly greater than every
+    /// value this manager (in this process or any other sharing the same
+    /// store file) has returned before.
+    fn next(&self) -> u64 {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&self.store_path)
+            .expect("failed to open nonce store");
+        file.lock_exclusive().expect("failed to lock nonce store");
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).ok();
+        let last = contents.trim().parse::<u64>().unwrap_or(0);
+
+        let now_ms = Utc::now().timestamp_millis() as u64;
+        let candidate = std::cmp::max(now_ms, last + 1);
+
+        file.set_len(0).expect("failed to truncate nonce store");
+        file.seek(SeekFrom::Start(0)).expect("failed to rewind nonce store");
+        write!(file, "{}", candidate).expect("failed to persist nonce");
+
+        file.unlock().ok();
+        candidate
+    }
+}
+
+/// A Kraken private-API client that owns a single `reqwest::Client` (and thus
+/// its TLS connection pool) plus the credentials and base URL needed to sign
+/// requests. Construct one with `KrakenClient::new` and reuse it for every
+/// call instead of standing up a fresh `reqwest::Client` per request.
+struct KrakenClient {
+    base_url: String,
+    api_key: String,
+    api_secret: String,
+    http: reqwest::Client,
+    nonce_manager: NonceManager,
+}
+
+impl KrakenClient {
+    /// Creates a client pointed at `base_url` (e.g. `https://api.kraken.com`,
+    /// or a sandbox/mock URL in tests) using the given API key/secret pair.
+    fn new(base_url: impl Into<String>, api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        let api_key = api_key.into();
+        let nonce_manager = NonceManager::new(&api_key);
+
+        Self {
+            base_url: base_url.into(),
+            api_key,
+            api_secret: api_secret.into(),
+            http: reqwest::Client::new(),
+            nonce_manager,
+        }
+    }
+
+    /// Builds, signs and sends a POST to a private Kraken endpoint, deserializing
+    /// the JSON response into `T`. Handles nonce generation and signing so callers
+    /// only need to supply the endpoint path and its extra form parameters.
+    async fn request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        mut params: HashMap<&str, String>,
+    ) -> Result<T, KrakenError> {
+        let url = format!("{}{}", self.base_url, endpoint);
+
+        let nonce = self.nonce_manager.next().to_string();
+        params.insert("nonce", nonce.clone());
+
+        let post_data = serde_urlencoded::to_string(&params)
+            .map_err(|e| KrakenError::Signature(format!("failed to encode post data: {}", e)))?;
+        let api_sign = generate_signature(&self.api_secret, &nonce, endpoint, &post_data)?;
+
+        let response = self
+            .http
+            .post(&url)
+            .header("API-Key", &self.api_key)
+            .header("API-Sign", api_sign)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(post_data)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(KrakenError::Api(vec![format!("request failed with status {}", status)]));
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Sends an unsigned GET to a public Kraken endpoint and deserializes the
+    /// JSON response into `T`. Public endpoints need no API key, secret or
+    /// nonce, unlike the private ones `request` handles.
+    async fn public_request<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &[(&str, &str)],
+    ) -> Result<T, KrakenError> {
+        let url = format!("{}{}", self.base_url, endpoint);
+
+        let response = self.http.get(&url).query(params).send().await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(KrakenError::Api(vec![format!("request failed with status {}", status)]));
+        }
+
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Fetches the current ticker (best bid/ask, last trade price) for `pair`.
+    async fn ticker(&self, pair: &str) -> Result<TickerInfo, KrakenError> {
+        let response: TickerResponse = self.public_request("/0/public/Ticker", &[("pair", pair)]).await?;
+
+        if !response.error.is_empty() {
+            return Err(KrakenError::Api(response.error));
+        }
+
+        response
+            .result
+            .and_then(|result| result.into_values().next())
+            .ok_or_else(|| KrakenError::Api(vec!["no ticker data returned".to_string()]))
+    }
+
+    /// Fetches the order book for `pair`, asking Kraken to return up to
+    /// `depth` levels per side.
+    async fn order_book(&self, pair: &str, depth: u32) -> Result<OrderBookInfo, KrakenError> {
+        let depth = depth.to_string();
+        let response: OrderBookResponse = self
+            .public_request("/0/public/Depth", &[("pair", pair), ("count", &depth)])
+            .await?;
+
+        if !response.error.is_empty() {
+            return Err(KrakenError::Api(response.error));
+        }
+
+        response
+            .result
+            .and_then(|result| result.into_values().next())
+            .ok_or_else(|| KrakenError::Api(vec!["no order book data returned".to_string()]))
+    }
+
+    /// Fetches the account balance for all assets held by this key.
+    async fn balance(&self) -> Result<HashMap<String, String>, KrakenError> {
+        let response: BalanceResponse = self.request("/0/private/Balance", HashMap::new()).await?;
+
+        if !response.error.is_empty() {
+            return Err(KrakenError::Api(response.error));
+        }
+
+        response
+            .result
+            .ok_or_else(|| KrakenError::Api(vec!["no balance data returned".to_string()]))
+    }
+
+    /// Submits an order built with `OrderRequest`, covering market, limit,
+    /// stop-loss and take-profit orders on any pair.
+    async fn add_order(&self, req: OrderRequest) -> Result<OrderResponse, KrakenError> {
+        let mut params = HashMap::new();
+        params.insert("pair", req.pair);
+        params.insert("type", req.side.as_kraken_str().to_string());
+        params.insert("ordertype", req.order_type.as_kraken_str().to_string());
+        params.insert("volume", req.volume);
+
+        // Kraken's `price` field means different things per order type: the
+        // limit price for `Limit`, but the trigger price for `StopLoss`/
+        // `TakeProfit`. `price2` is only meaningful for the `-limit` combo
+        // order types, which this API doesn't model yet.
+        match req.order_type {
+            OrderType::Limit => {
+                if let Some(price) = req.price {
+                    params.insert("price", price);
                 }
-                return Ok(Some(result));
-            } else {
-                println!("No balance data found.");
             }
-        } else {
-            println!("API returned errors: {:?}", balance.error);
+            OrderType::StopLoss | OrderType::TakeProfit => {
+                if let Some(stop_price) = req.stop_price {
+                    params.insert("price", stop_price);
+                }
+            }
+            OrderType::Market => {}
         }
-    } else {
-        println!("Request failed with status code: {}", status);
-    }
-    Ok(None)
-}
-
-// Function to place a market sell order for USD
-async fn place_market_order_usd(api_key: Arc<String>, api_secret: Arc<String>, volume: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let url = "https://api.kraken.com/0/private/AddOrder";
-    let endpoint = "/0/private/AddOrder";
-    
-    let client = reqwest::Client::new();
-    let nonce = format!("{}", Utc::now().timestamp_millis());
-    let mut params = HashMap::new();
-    params.insert("nonce", nonce.clone());
-    params.insert("ordertype", "market".to_string());
-    params.insert("type", "sell".to_string());
-    params.insert("volume", volume.clone());
-    params.insert("pair", "USDCUSD".to_string()); // Set trading pair to USDCUSD
-    
-    // Create post data string
-    let post_data = format!("nonce={}&ordertype=market&type=sell&volume={}&pair=USDCUSD", nonce, volume);
-    
-    // Generate API signature
-    let api_sign = generate_signature(&api_secret, &nonce, endpoint, &post_data);
-
-    // Print request details for debugging
-    println!("API Key: {}", api_key);
-    println!("API Sign: {}", api_sign);
-    println!("Post Data: {}", post_data);
-
-    // Send POST request to place market order
-    let response = client
-        .post(url)
-        .header("API-Key", api_key.as_str())
-        .header("API-Sign", api_sign)
-        .form(&params)
-        .send()
-        .await?;
-
-    // Print response status and body for debugging
-    let status = response.status();
-    let body = response.text().await?;
-    println!("Response status: {}", status);
-    println!("Response body: {}", body);
-
-    // Process the response if successful
-    if status.is_success() {
-        let order_response: OrderResponse = serde_json::from_str(&body)?;
-        if order_response.error.is_empty() {
-            println!("Market order placed successfully");
-        } else {
-            println!("API returned errors: {:?}", order_response.error);
+        if let Some(time_in_force) = req.time_in_force {
+            params.insert("timeinforce", time_in_force.as_kraken_str().to_string());
         }
-    } else {
-        println!("Request failed with status code: {}", status);
+        if let Some(user_ref) = req.user_ref {
+            params.insert("userref", user_ref.to_string());
+        }
+        if req.validate {
+            params.insert("validate", "true".to_string());
+        }
+
+        let response: OrderResponse = self.request("/0/private/AddOrder", params).await?;
+
+        if !response.error.is_empty() {
+            return Err(KrakenError::Api(response.error));
+        }
+
+        Ok(response)
     }
 
-    Ok(())
+    /// Convenience wrapper that places a market sell order for `volume` units
+    /// of USDC against USD.
+    async fn place_market_sell_usd(&self, volume: &str) -> Result<OrderResponse, KrakenError> {
+        let req = OrderRequest::new("USDCUSD", OrderSide::Sell, OrderType::Market, volume.to_string());
+        self.add_order(req).await
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let api_key = Arc::new("your_api_key".to_string());
-    let api_secret = Arc::new("your_api_secret".to_string());
+    let client = Arc::new(KrakenClient::new(
+        DEFAULT_BASE_URL,
+        "your_api_key",
+        "your_api_secret",
+    ));
 
     // Fetch balance task
     let balance_task = {
-        let api_key = Arc::clone(&api_key);
-        let api_secret = Arc::clone(&api_secret);
-        tokio::spawn(async move {
-            fetch_balance(&api_key, &api_secret).await
-        })
+        let client = Arc::clone(&client);
+        tokio::spawn(async move { client.balance().await })
     };
 
-    // Wait for the balance to be fetched
-    if let Ok(Ok(Some(balance))) = balance_task.await {
-        if let Some(usdc_balance) = balance.get("USDC") {
-            println!("USDC Balance: {}", usdc_balance);
-
-            // If there is enough balance, place a market sell order
-            if usdc_balance.parse::<f64>().unwrap() > 0.0 {
-                let volume = usdc_balance.clone();
-                let order_task = {
-                    let api_key = Arc::clone(&api_key);
-                    let api_secret = Arc::clone(&api_secret);
-                    tokio::spawn(async move {
-                        place_market_order_usd(api_key, api_secret, volume).await
-                    })
-                };
-
-                if let Err(e) = order_task.await {
-                    eprintln!("Error placing market order: {:?}", e);
-                }
-            } else {
-                println!("No USDC balance to sell.");
+    // Quote the market before deciding whether a sell makes sense.
+    match client.ticker("USDCUSD").await {
+        Ok(ticker) => {
+            if let (Some(bid), Some(ask), Some(last)) =
+                (ticker.bid.first(), ticker.ask.first(), ticker.last_trade.first())
+            {
+                println!("USDCUSD bid {} / ask {} (last trade {})", bid, ask, last);
+            }
+        }
+        Err(e) => eprintln!("Error fetching ticker: {}", e),
+    }
+    match client.order_book("USDCUSD", 5).await {
+        Ok(book) => {
+            println!(
+                "USDCUSD order book depth: {} bids, {} asks",
+                book.bids.len(),
+                book.asks.len()
+            );
+            if let Some(OrderBookLevel(price, volume, timestamp)) = book.bids.first() {
+                println!("Best bid: {} (volume {}, as of {})", price, volume, timestamp);
             }
-        } else {
-            println!("No USDC balance found.");
+            if let Some(OrderBookLevel(price, volume, timestamp)) = book.asks.first() {
+                println!("Best ask: {} (volume {}, as of {})", price, volume, timestamp);
+            }
+        }
+        Err(e) => eprintln!("Error fetching order book: {}", e),
+    }
+
+    let balance = match balance_task.await {
+        Ok(Ok(balance)) => balance,
+        Ok(Err(e)) => {
+            eprintln!("Error fetching balance: {}", e);
+            return;
+        }
+        Err(e) => {
+            eprintln!("Balance task panicked: {:?}", e);
+            return;
+        }
+    };
+
+    let Some(usdc_balance) = balance.get("USDC") else {
+        println!("No USDC balance found.");
+        return;
+    };
+    println!("USDC Balance: {}", usdc_balance);
+
+    // If there is enough balance, place a market sell order
+    if usdc_balance.parse::<f64>().unwrap() > 0.0 {
+        let volume = usdc_balance.clone();
+
+        // Sanity-check signing and parameters with validate-only orders before
+        // touching real funds, covering the range of orders this bot might
+        // place around a sell: a resting limit sell, a stop-loss and a
+        // take-profit to bracket the position, and a buy-back if it dips.
+        let limit_check = OrderRequest::new("USDCUSD", OrderSide::Sell, OrderType::Limit, volume.clone())
+            .price("1.00")
+            .time_in_force(TimeInForce::GoodTillCancel)
+            .user_ref(1)
+            .validate(true);
+        match client.add_order(limit_check).await {
+            Ok(_) => println!("Validate-only limit order accepted by Kraken"),
+            Err(e) => eprintln!("Validate-only limit order failed: {}", e),
+        }
+
+        let stop_loss_check = OrderRequest::new("USDCUSD", OrderSide::Sell, OrderType::StopLoss, volume.clone())
+            .stop_price("0.95")
+            .user_ref(2)
+            .validate(true);
+        match client.add_order(stop_loss_check).await {
+            Ok(_) => println!("Validate-only stop-loss order accepted by Kraken"),
+            Err(e) => eprintln!("Validate-only stop-loss order failed: {}", e),
+        }
+
+        let take_profit_check = OrderRequest::new("USDCUSD", OrderSide::Sell, OrderType::TakeProfit, volume.clone())
+            .stop_price("1.05")
+            .time_in_force(TimeInForce::GoodTillDate)
+            .user_ref(3)
+            .validate(true);
+        match client.add_order(take_profit_check).await {
+            Ok(_) => println!("Validate-only take-profit order accepted by Kraken"),
+            Err(e) => eprintln!("Validate-only take-profit order failed: {}", e),
+        }
+
+        let buy_back_check = OrderRequest::new("USDCUSD", OrderSide::Buy, OrderType::Limit, volume.clone())
+            .price("0.98")
+            .time_in_force(TimeInForce::ImmediateOrCancel)
+            .user_ref(4)
+            .validate(true);
+        match client.add_order(buy_back_check).await {
+            Ok(_) => println!("Validate-only buy-back order accepted by Kraken"),
+            Err(e) => eprintln!("Validate-only buy-back order failed: {}", e),
+        }
+
+        let order_task = {
+            let client = Arc::clone(&client);
+            tokio::spawn(async move { client.place_market_sell_usd(&volume).await })
+        };
+
+        match order_task.await {
+            Ok(Ok(response)) => match &response.result {
+                Some(result) => println!("Market order placed successfully: {:?}", result),
+                None => println!("Market order placed successfully"),
+            },
+            Ok(Err(e)) => eprintln!("Error placing market order: {}", e),
+            Err(e) => eprintln!("Order task panicked: {:?}", e),
         }
     } else {
-        eprintln!("Error fetching balance or no balance available.");
+        println!("No USDC balance to sell.");
     }
 }